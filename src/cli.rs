@@ -0,0 +1,82 @@
+//! Command-line argument parsing.
+
+use clap::ArgMatches;
+use url::Url;
+
+use crate::net::{parse_url_or_file_path, resolve_url};
+
+/// Resolves the positional document argument to a URL, falling back to `None` (the caller
+/// supplies a default document) when it wasn't given on the command line.
+///
+/// Accepts `http://`, `https://`, and `file://` URLs, as well as bare local paths, which are
+/// resolved relative to the current working directory.
+pub fn document_url_from_args(arg_matches: &ArgMatches) -> Option<Url> {
+    arg_matches
+        .value_of("input")
+        .map(|input| parse_url_or_file_path(input).expect("invalid document url or path"))
+}
+
+/// Resolves the `--stylesheet` arguments to URLs relative to the document's URL.
+pub fn stylesheet_urls_from_args(arg_matches: &ArgMatches, document_url: &Url) -> Option<Vec<Url>> {
+    arg_matches.values_of("stylesheet").map(|values| {
+        values
+            .map(|value| {
+                Url::parse(value)
+                    .or_else(|_| resolve_url(document_url, value))
+                    .expect("invalid stylesheet url or path")
+            })
+            .collect()
+    })
+}
+
+pub fn dump_layout_tree(arg_matches: &ArgMatches) -> bool {
+    arg_matches.is_present("dump-layout")
+}
+
+pub fn inner_window_width(arg_matches: &ArgMatches) -> Option<f32> {
+    arg_matches
+        .value_of("width")
+        .map(|width| width.parse().expect("width must be a number"))
+}
+
+pub fn inner_window_height(arg_matches: &ArgMatches) -> Option<f32> {
+    arg_matches
+        .value_of("height")
+        .map(|height| height.parse().expect("height must be a number"))
+}
+
+pub fn scale_factor(arg_matches: &ArgMatches) -> Option<f32> {
+    arg_matches
+        .value_of("scale-factor")
+        .map(|scale_factor| scale_factor.parse().expect("scale-factor must be a number"))
+}
+
+pub fn setup_and_get_cli_args<'a>() -> ArgMatches<'a> {
+    clap::App::new("kosmonaut")
+        .about("A browser engine, written in Rust.")
+        .arg(
+            clap::Arg::with_name("input")
+                .help("URL (http(s):// or file://) or local path of the document to load")
+                .index(1),
+        )
+        .arg(
+            clap::Arg::with_name("stylesheet")
+                .long("stylesheet")
+                .help("An additional author stylesheet to apply, as a URL or local path")
+                .multiple(true)
+                .number_of_values(1),
+        )
+        .arg(
+            clap::Arg::with_name("dump-layout")
+                .long("dump-layout")
+                .help("Dumps the layout tree to stdout instead of opening a window"),
+        )
+        .arg(clap::Arg::with_name("width").long("width").takes_value(true))
+        .arg(clap::Arg::with_name("height").long("height").takes_value(true))
+        .arg(
+            clap::Arg::with_name("scale-factor")
+                .long("scale-factor")
+                .takes_value(true),
+        )
+        .get_matches()
+}
@@ -0,0 +1,65 @@
+//! Selecting one of Kosmonaut's bundled font families by name.
+//!
+//! Kosmonaut doesn't load arbitrary system fonts -- it ships with a small, fixed set of bundled
+//! families, matched case-insensitively. `CharHandle::new` selects the original hardcoded
+//! default; `CharHandle::for_family` generalizes that to selecting by name, failing for anything
+//! Kosmonaut doesn't bundle so `FontContext::resolve` can fall through to the next family in a
+//! `font-family` list.
+
+use gl::Gl;
+
+/// Families Kosmonaut actually ships glyphs for. Every other requested family fails to load,
+/// which is exactly what lets `FontContext::resolve` fall through a `font-family` list.
+const BUNDLED_FAMILIES: &[&str] = &["sans-serif", "monospace", "Arial", "Helvetica"];
+
+#[derive(Debug)]
+pub struct CharLoadError(String);
+
+impl std::fmt::Display for CharLoadError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "no bundled font for family {:?}", self.0)
+    }
+}
+
+impl std::error::Error for CharLoadError {}
+
+/// A bundled font family matched by name. It holds only the family's name; no glyph data is
+/// loaded or rasterized here.
+pub struct CharHandle {
+    family: String,
+}
+
+impl CharHandle {
+    /// Selects Kosmonaut's original default bundled family -- equivalent to
+    /// `for_family(gl, "sans-serif", false, false)`.
+    pub fn new(gl: &Gl) -> CharHandle {
+        CharHandle::for_family(gl, "sans-serif", false, false)
+            .expect("the default bundled family must always be loadable")
+    }
+
+    /// Selects the bundled font for `family`, if Kosmonaut ships one. `bold`/`italic` are accepted
+    /// for parity with CSS font properties, but every bundled family is currently a single
+    /// regular-weight, upright face. `gl` is not called through: matching the name is all this
+    /// does.
+    pub fn for_family(
+        _gl: &Gl,
+        family: &str,
+        _bold: bool,
+        _italic: bool,
+    ) -> Result<CharHandle, CharLoadError> {
+        if BUNDLED_FAMILIES
+            .iter()
+            .any(|bundled| bundled.eq_ignore_ascii_case(family))
+        {
+            Ok(CharHandle {
+                family: family.to_owned(),
+            })
+        } else {
+            Err(CharLoadError(family.to_owned()))
+        }
+    }
+
+    pub fn family(&self) -> &str {
+        &self.family
+    }
+}
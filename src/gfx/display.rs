@@ -0,0 +1,75 @@
+//! Display-list construction: flattens a laid-out box tree into the ordered list of paint
+//! fragments `MasterPainter` draws each frame.
+//!
+//! Each fragment carries its (scaled) content-box rectangle and, for every box with a node of its
+//! own (not anonymous boxes), the `FontGroup` its text is drawn with -- resolved via
+//! `FontContext`, which walks the node's computed `font-family` list and caches the result,
+//! instead of every fragment sharing one hardcoded font.
+
+use std::sync::Arc;
+
+use crate::gfx::font::{FontContext, FontGroup, FontStyle};
+use crate::layout::LayoutBox;
+
+pub struct DisplayFragment {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+    /// `None` for anonymous boxes, which have no node and so no font of their own. An `Arc`, not
+    /// an `Rc`, because the `DisplayList` this fragment belongs to is sent across the channel to
+    /// the paint task's own thread.
+    pub font_group: Option<Arc<FontGroup>>,
+}
+
+pub struct DisplayList {
+    pub fragments: Vec<DisplayFragment>,
+}
+
+/// Builds `layout_tree`'s display list, resolving each box's `font-family` via `font_context`
+/// instead of drawing every fragment with one hardcoded font.
+pub fn build_display_list(
+    layout_tree: &LayoutBox,
+    font_context: &FontContext,
+    scale_factor: f32,
+) -> DisplayList {
+    let mut fragments = Vec::new();
+    push_fragments(layout_tree, font_context, scale_factor, &mut fragments);
+    DisplayList { fragments }
+}
+
+fn push_fragments(
+    layout_box: &LayoutBox,
+    font_context: &FontContext,
+    scale_factor: f32,
+    fragments: &mut Vec<DisplayFragment>,
+) {
+    let dimensions = layout_box.dimensions();
+    let content = &dimensions.content;
+    fragments.push(DisplayFragment {
+        x: content.x * scale_factor,
+        y: content.y * scale_factor,
+        width: content.width * scale_factor,
+        height: content.height * scale_factor,
+        font_group: font_style(layout_box).map(|style| font_context.resolve(&style)),
+    });
+    for child in layout_box.children() {
+        push_fragments(child, font_context, scale_factor, fragments);
+    }
+}
+
+/// The `FontStyle` `layout_box`'s node's text should be drawn with, derived from its computed
+/// `font-family`. `None` for anonymous boxes, which have no node of their own.
+///
+/// TODO: derive `bold`/`italic` from computed `font-weight`/`font-style` once those are threaded
+/// through `ComputedValues`; every font is currently resolved as regular, upright.
+fn font_style(layout_box: &LayoutBox) -> Option<FontStyle> {
+    let node = layout_box.node()?;
+    let computed = node.computed_values();
+    let computed = computed.as_ref()?;
+    Some(FontStyle {
+        families: computed.font_family.clone(),
+        bold: false,
+        italic: false,
+    })
+}
@@ -0,0 +1,94 @@
+//! CSS font selection.
+//!
+//! `CharHandle` used to be a single hardcoded-font glyph handle, with no notion of CSS
+//! `font-family` -- text that asked for a specific or missing family just couldn't be
+//! satisfied. `FontContext` generalizes that into real font selection: given a computed
+//! `font-family` list, it walks the requested families in order, falls back through them, and
+//! finally through a built-in last-resort family list when none match.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use gl::Gl;
+
+use crate::gfx::char::CharHandle;
+
+/// Tried, in order, when every family a `font-family` list names is unavailable -- a browser's
+/// "last resort" font.
+const LAST_RESORT_FAMILIES: &[&str] = &["Arial", "Helvetica", "sans-serif"];
+
+/// A resolved `font-family` list plus the style variants that affect which glyphs are needed.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct FontStyle {
+    pub families: Vec<String>,
+    pub bold: bool,
+    pub italic: bool,
+}
+
+/// A resolved, ready-to-use set of glyphs for a `font-family` list: the first family that could
+/// actually be loaded, falling back through the rest of the list and then through
+/// `LAST_RESORT_FAMILIES` when none of the requested families are available.
+pub struct FontGroup {
+    pub resolved_family: String,
+    pub char_handle: CharHandle,
+}
+
+/// Resolves `font-family` lists to `FontGroup`s, caching by `(family-list, style)` so repeated
+/// lookups during display-list building -- one per styled text run -- are cheap.
+///
+/// Owns its `Gl` handle (rather than borrowing one) so it isn't tied to the lifetime of any other
+/// clone of the same handle -- e.g. one handed off to the paint task. `CharHandle::for_family`
+/// doesn't actually call through it yet (see its own doc comment), so this is inert for now:
+/// `FontContext` is built and used entirely from `run_event_loop`'s thread, which never makes the
+/// GL context current, so real glyph rasterization can't happen through `self.gl` as-is. Once
+/// `CharHandle` does real GL work, `FontContext` needs to move onto the paint task's thread
+/// (alongside `MasterPainter`, where a context actually is current) rather than gain one of its
+/// own here.
+pub struct FontContext {
+    gl: Gl,
+    cache: RefCell<HashMap<FontStyle, Arc<FontGroup>>>,
+}
+
+impl FontContext {
+    pub fn new(gl: Gl) -> FontContext {
+        FontContext {
+            gl,
+            cache: RefCell::new(HashMap::new()),
+        }
+    }
+
+    /// Resolves `style`'s family list to a `FontGroup`, walking the requested families in order,
+    /// then `LAST_RESORT_FAMILIES`, and caching the result for subsequent lookups of the same
+    /// `style`.
+    pub fn resolve(&self, style: &FontStyle) -> Arc<FontGroup> {
+        if let Some(cached) = self.cache.borrow().get(style) {
+            return Arc::clone(cached);
+        }
+
+        let resolved = style
+            .families
+            .iter()
+            .map(String::as_str)
+            .chain(LAST_RESORT_FAMILIES.iter().copied())
+            .find_map(|family| {
+                CharHandle::for_family(&self.gl, family, style.bold, style.italic)
+                    .ok()
+                    .map(|char_handle| {
+                        Arc::new(FontGroup {
+                            resolved_family: family.to_owned(),
+                            char_handle,
+                        })
+                    })
+            })
+            .expect(
+                "no font family in the fallback list -- including the last resort families -- \
+                 could be loaded",
+            );
+
+        self.cache
+            .borrow_mut()
+            .insert(style.clone(), Arc::clone(&resolved));
+        resolved
+    }
+}
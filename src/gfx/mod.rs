@@ -0,0 +1,4 @@
+pub mod char;
+pub mod display;
+pub mod font;
+pub mod paint_task;
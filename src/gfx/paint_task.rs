@@ -0,0 +1,109 @@
+//! A dedicated paint/rasterization thread.
+//!
+//! Previously the event loop called `paint(...)` synchronously on the UI thread, so layout and
+//! GL submission blocked window event processing. This task owns `MasterPainter` and the GL
+//! context instead, receiving a `DisplayList` plus viewport/scale over an `mpsc` channel; the
+//! main thread keeps handling resize/scale events while a paint is in flight. If several
+//! messages pile up while the task is busy drawing, only the latest `Paint` (and the latest
+//! `Resize`) actually matter, so they're coalesced before drawing.
+
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread::JoinHandle;
+
+use gl::Gl;
+use glutin::dpi::PhysicalSize;
+use glutin::{NotCurrent, PossiblyCurrent, WindowedContext};
+
+use crate::gfx::display::DisplayList;
+use crate::gfx::paint::MasterPainter;
+use crate::gfx::resize_window;
+
+pub enum PaintMsg {
+    Paint(DisplayList, f32),
+    Resize(PhysicalSize<u32>),
+    Exit,
+}
+
+/// The main thread's handle to the paint task: sends it work, never blocks waiting for it, except
+/// when dropped, which waits for the task to exit.
+pub struct PaintTaskHandle {
+    sender: Sender<PaintMsg>,
+    join_handle: Option<JoinHandle<()>>,
+}
+
+impl PaintTaskHandle {
+    /// Spawns the paint task, handing it ownership of `windowed_context` (not-current, so it
+    /// carries no thread affinity yet) and `painter`. The task makes the context current on its
+    /// own thread and keeps it there for the rest of the program's life.
+    pub fn spawn(
+        windowed_context: WindowedContext<NotCurrent>,
+        gl: Gl,
+        painter: MasterPainter,
+    ) -> PaintTaskHandle {
+        let (sender, receiver) = mpsc::channel();
+        let join_handle = std::thread::Builder::new()
+            .name("kosmonaut-paint".to_owned())
+            .spawn(move || {
+                let windowed_context = unsafe {
+                    windowed_context
+                        .make_current()
+                        .expect("failed to make GL context current on paint task")
+                };
+                run_paint_task(windowed_context, gl, painter, receiver);
+            })
+            .expect("failed to spawn paint task");
+        PaintTaskHandle {
+            sender,
+            join_handle: Some(join_handle),
+        }
+    }
+
+    pub fn paint(&self, display_list: DisplayList, scale: f32) {
+        let _ = self.sender.send(PaintMsg::Paint(display_list, scale));
+    }
+
+    pub fn resize(&self, size: PhysicalSize<u32>) {
+        let _ = self.sender.send(PaintMsg::Resize(size));
+    }
+}
+
+impl Drop for PaintTaskHandle {
+    fn drop(&mut self) {
+        let _ = self.sender.send(PaintMsg::Exit);
+        if let Some(join_handle) = self.join_handle.take() {
+            let _ = join_handle.join();
+        }
+    }
+}
+
+fn run_paint_task(
+    windowed_context: WindowedContext<PossiblyCurrent>,
+    gl: Gl,
+    mut painter: MasterPainter,
+    receiver: Receiver<PaintMsg>,
+) {
+    while let Ok(first) = receiver.recv() {
+        let mut latest_paint = None;
+        let mut latest_resize = None;
+        let mut should_exit = false;
+        // Drain whatever else has queued up since the last draw; only the newest paint and
+        // resize requests matter, everything older is already stale.
+        for msg in std::iter::once(first).chain(receiver.try_iter()) {
+            match msg {
+                PaintMsg::Paint(display_list, scale) => latest_paint = Some((display_list, scale)),
+                PaintMsg::Resize(size) => latest_resize = Some(size),
+                PaintMsg::Exit => should_exit = true,
+            }
+        }
+
+        if let Some(size) = latest_resize {
+            resize_window(&gl, &windowed_context, &size);
+        }
+        if let Some((display_list, _scale)) = latest_paint {
+            painter.paint(&windowed_context, &display_list);
+        }
+        if should_exit {
+            break;
+        }
+    }
+}
@@ -0,0 +1,132 @@
+//! Parallel layout.
+//!
+//! Mirrors Servo's parallel flow traversal: a top-down pass assigns each box its available
+//! inline-size (width) from its parent/containing block, then a bottom-up pass computes
+//! block-size (height) and content position from children, once they're laid out. Siblings are
+//! independent in the top-down pass, and subtrees are independent in the bottom-up pass, so both
+//! are dispatched onto a work-stealing pool (`rayon`) instead of walked by a single thread.
+//!
+//! `BoxType::Block`/`Inline` carry a `NodeRef`, the DOM's `Rc`-backed node handle, which is
+//! `!Send` -- so `LayoutBox` is `!Send` too, and a closure capturing one by `&mut` can't cross
+//! `rayon::Scope::spawn`'s `Send` bound directly. `SendPtr` works around that: each spawned
+//! closure is handed a raw pointer to a *distinct* element of the parent's `children`, so (unlike
+//! a shared `&LayoutBox`) there's no aliasing for `rayon`'s `Send` check to actually be protecting
+//! against -- see its doc comment for the safety argument.
+//!
+//! Invariant: a box's inline-size must be final before any child's top-down pass runs, and all of
+//! a box's children's block-sizes must be final before its own bottom-up step runs.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::layout::{compute_intrinsic_sizes, BoxType, LayoutBox};
+
+/// A raw pointer to a `LayoutBox`, asserted `Send` so it can be handed to `rayon::Scope::spawn`
+/// even though `LayoutBox` itself isn't (it holds a `!Send` `NodeRef`).
+///
+/// Safety relies on every `SendPtr` spawned from one `rayon::scope` pointing at a distinct
+/// element of the same `Vec` (one per child), never re-derived from an already-dereferenced
+/// pointer, and never outliving the `scope` call that created it -- so the exclusive `&mut`
+/// access each spawned closure reconstructs from it never aliases another live reference.
+struct SendPtr(*mut LayoutBox);
+
+unsafe impl Send for SendPtr {}
+
+/// Lays out `root` (and everything beneath it) in a viewport `viewport_width` CSS pixels wide.
+/// `viewport_height` and `scale_factor` are accepted but do not affect the result.
+pub fn global_layout(
+    root: &mut LayoutBox,
+    viewport_width: f32,
+    viewport_height: f32,
+    scale_factor: f32,
+) {
+    root.dimensions.content.width = viewport_width;
+    // Intrinsic sizes are needed to resolve shrink-to-fit (inline-level) widths below, and can
+    // only be computed bottom-up, so this has to happen before `assign_inline_sizes`'s top-down
+    // walk.
+    compute_intrinsic_sizes(root);
+    assign_inline_sizes(root);
+    compute_block_sizes(root);
+    let _ = (viewport_height, scale_factor);
+}
+
+/// Top-down pass: gives each child its available inline-size from `layout_box`'s already-final
+/// content width, then recurses into children in parallel. Siblings don't depend on each other,
+/// only on the parent's width, which is why they can run concurrently -- see the module docs for
+/// how that's reconciled with `LayoutBox` being `!Send`.
+///
+/// A block-level child with `width: auto` fills the available width, per CSS2's normal-flow
+/// sizing rules. An inline-level (or anonymous) child instead shrink-to-fits: as wide as its
+/// max-content, but never wider than what's available nor narrower than its min-content, using
+/// the sizes `compute_intrinsic_sizes` already cached on it.
+fn assign_inline_sizes(layout_box: &mut LayoutBox) {
+    let available_width = layout_box.dimensions.content.width;
+    let content_x = layout_box.dimensions.content.x;
+    for child in &mut layout_box.children {
+        child.dimensions.content.width = match child.box_type {
+            BoxType::Block(_) => available_width,
+            BoxType::Inline(_) | BoxType::Anonymous => {
+                let intrinsic = child
+                    .intrinsic_sizes
+                    .expect("compute_intrinsic_sizes must run before assign_inline_sizes");
+                intrinsic
+                    .max_content
+                    .min(available_width)
+                    .max(intrinsic.min_content)
+            }
+        };
+        child.dimensions.content.x = content_x;
+    }
+    rayon::scope(|scope| {
+        for child in &mut layout_box.children {
+            let child = SendPtr(child as *mut LayoutBox);
+            scope.spawn(move |_| {
+                // SAFETY: `child` points at one element of `layout_box.children`; every other
+                // spawned closure this loop iteration points at a different element, and nothing
+                // else touches `layout_box.children` until `scope` returns.
+                assign_inline_sizes(unsafe { &mut *child.0 });
+            });
+        }
+    });
+}
+
+/// Bottom-up pass: computes `layout_box`'s block-size and each child's vertical content position,
+/// once every child's own bottom-up step is final.
+///
+/// The children's bottom-up steps run in parallel inside a `rayon::scope` on its work-stealing
+/// pool, and this box's own height is computed after that scope has joined all of them. Each
+/// child also decrements an atomic counter of unfinished children when it finishes; nothing waits
+/// on that counter -- it is only checked to be zero (in debug builds) after the join.
+///
+/// A child's own step positions its descendants relative to where the child was at the time, so
+/// placing the child here moves its whole subtree by the same amount.
+fn compute_block_sizes(layout_box: &mut LayoutBox) {
+    let unfinished_children = AtomicUsize::new(layout_box.children.len());
+    rayon::scope(|scope| {
+        for child in &mut layout_box.children {
+            let unfinished_children = &unfinished_children;
+            let child = SendPtr(child as *mut LayoutBox);
+            scope.spawn(move |_| {
+                // SAFETY: see `assign_inline_sizes` above -- same one-distinct-element-per-
+                // closure argument applies here.
+                compute_block_sizes(unsafe { &mut *child.0 });
+                unfinished_children.fetch_sub(1, Ordering::AcqRel);
+            });
+        }
+    });
+    debug_assert_eq!(unfinished_children.load(Ordering::Acquire), 0);
+
+    let mut next_child_y = layout_box.dimensions.content.y;
+    let mut content_height = 0.0;
+    for child in &mut layout_box.children {
+        let margin_box_height = child.dimensions.margin_box().height;
+        let dy = next_child_y - child.dimensions.content.y;
+        let mut subtree = vec![child];
+        while let Some(descendant) = subtree.pop() {
+            descendant.dimensions.content.y += dy;
+            subtree.extend(descendant.children.iter_mut());
+        }
+        next_child_y += margin_box_height;
+        content_height += margin_box_height;
+    }
+    layout_box.dimensions.content.height = content_height;
+}
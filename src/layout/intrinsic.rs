@@ -0,0 +1,91 @@
+//! Intrinsic inline-size: a box's min-content and max-content contribution, independent of its
+//! containing block. This is what `width: auto`/`fit-content` shrink-to-fit sizing,
+//! `inline-block`, and (eventually) tables and floats resolve against, analogous to Servo's
+//! `bk_intrinsic` box kind carrying a cached intrinsic size.
+//!
+//! TODO(#chunk0-5 follow-up): Kosmonaut has no replaced-element `BoxType` yet (e.g. `<img>`), so
+//! the "replaced boxes have a fixed intrinsic size" case below is unreachable until one exists.
+
+use crate::layout::{BoxType, LayoutBox};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct IntrinsicSizes {
+    /// The narrowest `self` can be laid out at without overflowing its content, e.g. the widest
+    /// unbreakable run (a word, a replaced box) anywhere inside it.
+    pub min_content: f32,
+    /// How wide `self` would be if it were never forced to wrap or shrink.
+    pub max_content: f32,
+}
+
+impl IntrinsicSizes {
+    fn fixed(size: f32) -> IntrinsicSizes {
+        IntrinsicSizes {
+            min_content: size,
+            max_content: size,
+        }
+    }
+}
+
+/// Computes, and caches on `layout_box`, the intrinsic sizes of `layout_box` and everything
+/// beneath it. Must run bottom-up: a box's intrinsic size is derived entirely from its children's,
+/// never from its containing block.
+pub fn compute_intrinsic_sizes(layout_box: &mut LayoutBox) -> IntrinsicSizes {
+    if layout_box.children.is_empty() {
+        // Every childless box contributes nothing in either direction: Kosmonaut has no replaced
+        // boxes, and a text box's text is not measured here.
+        let sizes = IntrinsicSizes::fixed(0.0);
+        layout_box.intrinsic_sizes = Some(sizes);
+        return sizes;
+    }
+
+    let child_sizes: Vec<IntrinsicSizes> = layout_box
+        .children
+        .iter_mut()
+        .map(|child| {
+            let sizes = compute_intrinsic_sizes(child);
+            if percentage_width(&child.box_type) {
+                // A percentage width depends on the containing block, which intrinsic sizing
+                // (by definition) doesn't know yet, so such a child contributes 0 to its
+                // container's min-content per https://www.w3.org/TR/css-sizing-3/#min-content.
+                IntrinsicSizes {
+                    min_content: 0.0,
+                    ..sizes
+                }
+            } else {
+                sizes
+            }
+        })
+        .collect();
+
+    let sizes = match layout_box.box_type {
+        // A block's min/max-content is just the largest of its children's -- it never lets
+        // children sit side-by-side.
+        BoxType::Block(_) => IntrinsicSizes {
+            min_content: child_sizes.iter().map(|s| s.min_content).fold(0.0, f32::max),
+            max_content: child_sizes.iter().map(|s| s.max_content).fold(0.0, f32::max),
+        },
+        // An inline (or anonymous, inline-flow-containing) box folds its children into a single
+        // line: min-content is still the widest unbreakable child, but max-content is the sum of
+        // every child laid end-to-end.
+        BoxType::Inline(_) | BoxType::Anonymous => IntrinsicSizes {
+            min_content: child_sizes.iter().map(|s| s.min_content).fold(0.0, f32::max),
+            max_content: child_sizes.iter().map(|s| s.max_content).sum(),
+        },
+    };
+    layout_box.intrinsic_sizes = Some(sizes);
+    sizes
+}
+
+/// Whether the node behind `box_type` has a percentage `width`. Anonymous boxes have no node and
+/// are never percentage-sized.
+fn percentage_width(box_type: &BoxType) -> bool {
+    let node = match box_type {
+        BoxType::Block(node) | BoxType::Inline(node) => node,
+        BoxType::Anonymous => return false,
+    };
+    let computed = node.computed_values();
+    let computed = computed
+        .as_ref()
+        .expect("box must be styled before intrinsic sizing");
+    computed.width.is_percentage()
+}
@@ -5,50 +5,83 @@
 use crate::dom::tree::NodeRef;
 use crate::layout::BoxType::Anonymous;
 use crate::style::values::computed::Display;
+use std::io::Write;
 use std::mem::discriminant;
 
+mod global;
+mod intrinsic;
+pub use global::global_layout;
+pub use intrinsic::{compute_intrinsic_sizes, IntrinsicSizes};
+
 /// Takes a DOM node and builds the corresponding layout tree of it and its children.
+///
+/// Usually this is a single box, but if `node` is itself inline-level and has an in-flow
+/// block-level box nested somewhere inside it, that single box isn't enough to represent the
+/// result (see `build_layout_tree_fragments`) -- in that rare case (effectively only possible at
+/// the document root, since every other inline-level node is handled by its block-level ancestor
+/// instead) the fragments are wrapped in a synthetic anonymous box so callers still get one root.
 pub fn build_layout_tree(node: NodeRef) -> Option<LayoutBox> {
+    let mut fragments = build_layout_tree_fragments(node);
+    match fragments.len() {
+        0 => None,
+        1 => fragments.pop(),
+        _ => {
+            let mut root = LayoutBox::new(BoxType::Anonymous);
+            root.children = fragments;
+            Some(root)
+        }
+    }
+}
+
+/// Builds the layout box(es) for `node` and its children, returned as a flat list of completed,
+/// sibling fragments of `node`.
+///
+/// Almost always this is exactly one fragment: `node`'s own box, with all of its children laid
+/// out beneath it. It's more than one when `node` is inline-level and an in-flow block-level box
+/// is nested inside it (directly, or inside one of `node`'s own inline-level descendants) --
+/// per https://www.w3.org/TR/CSS2/visuren.html#box-gen ("When an inline box contains an in-flow
+/// block-level box..."), an inline box can't directly contain a block box, so `node`'s box gets
+/// split around it instead: `[fragment-before, block, ..., fragment-after]`. The caller (`node`'s
+/// block-level ancestor, found by recursing back up through the fragment lists) is the one that
+/// actually places the block fragment(s) at the block level and reopens an anonymous run for
+/// whatever inline content comes after.
+fn build_layout_tree_fragments(node: NodeRef) -> Vec<LayoutBox> {
     let computed_opt = &*node.computed_values();
     let computed_values = computed_opt
         .as_ref()
         .expect("layout called on a node that has not yet acquired computed values");
-    let mut layout_box = match computed_values.display {
+    let mut current = match computed_values.display {
         Display::Block => LayoutBox::new(BoxType::Block(node.clone())),
         Display::Inline => LayoutBox::new(BoxType::Inline(node.clone())),
-        Display::None => {
-            return None;
-        }
+        Display::None => return Vec::new(),
     };
 
+    let mut completed_fragments = Vec::new();
     for child in node.children() {
-        let child_computed_opt = &*child.computed_values();
-        let child_computed_values = child_computed_opt
-            .as_ref()
-            .expect("layout called on a node that has not yet acquired computed values");
-        match child_computed_values.display {
-            Display::Block => match build_layout_tree(child.clone()) {
-                // TODO: We don't handle the case where a block-flow child box is added to an inline
-                // box.  This current behavior is wrong.  To fix, see: https://www.w3.org/TR/CSS2/visuren.html#box-gen
-                // Namely, the paragraph that begins with "When an inline box contains an in-flow block-level box"
-                Some(child_box) => layout_box.children.push(child_box),
-                None => {}
-            },
-            Display::Inline => match build_layout_tree(child.clone()) {
-                Some(child_box) => layout_box.get_inline_container().children.push(child_box),
-                None => {}
-            },
-            Display::None => {}
+        for fragment in build_layout_tree_fragments(child.clone()) {
+            if fragment.is_block() && !current.is_block() {
+                // `current` is inline-level and can't directly contain a block-level fragment:
+                // close it off, place the block fragment beside it (not inside it), and open a
+                // fresh fragment of `node` for whatever inline content follows.
+                let box_type = current.box_type.clone();
+                completed_fragments.push(std::mem::replace(&mut current, LayoutBox::new(box_type)));
+                completed_fragments.push(fragment);
+            } else if fragment.is_block() {
+                current.children.push(fragment);
+            } else {
+                current.get_inline_container().children.push(fragment);
+            }
         }
     }
-    return Some(layout_box);
+    completed_fragments.push(current);
+    completed_fragments
 }
 
 /// https://www.w3.org/TR/2018/WD-css-box-3-20181218/#box-model
 #[derive(Clone, Debug, Default)]
-struct Dimensions {
+pub(crate) struct Dimensions {
     // Position of the content area relative to the document origin:
-    content: Rect,
+    pub(crate) content: Rect,
 
     // Surrounding edges:
     padding: EdgeSizes,
@@ -56,12 +89,29 @@ struct Dimensions {
     margin: EdgeSizes,
 }
 
+impl Dimensions {
+    /// The content area expanded by padding.
+    fn padding_box(&self) -> Rect {
+        self.content.expanded_by(&self.padding)
+    }
+
+    /// The padding box expanded by the border.
+    fn border_box(&self) -> Rect {
+        self.padding_box().expanded_by(&self.border)
+    }
+
+    /// The border box expanded by the margin; the total space this box occupies.
+    fn margin_box(&self) -> Rect {
+        self.border_box().expanded_by(&self.margin)
+    }
+}
+
 #[derive(Clone, Debug, Default)]
 pub struct Rect {
-    x: f32,
-    y: f32,
-    width: f32,
-    height: f32,
+    pub(crate) x: f32,
+    pub(crate) y: f32,
+    pub(crate) width: f32,
+    pub(crate) height: f32,
 }
 
 #[derive(Clone, Debug, Default)]
@@ -72,11 +122,26 @@ pub struct EdgeSizes {
     bottom: f32,
 }
 
+impl Rect {
+    /// Returns this rect expanded on each edge by the corresponding edge size.
+    fn expanded_by(&self, edge: &EdgeSizes) -> Rect {
+        Rect {
+            x: self.x - edge.left,
+            y: self.y - edge.top,
+            width: self.width + edge.left + edge.right,
+            height: self.height + edge.top + edge.bottom,
+        }
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct LayoutBox {
     dimensions: Dimensions,
     box_type: BoxType,
     children: Vec<LayoutBox>,
+    /// This box's min-content/max-content inline sizes, cached by `compute_intrinsic_sizes`.
+    /// `None` until that pass has run.
+    intrinsic_sizes: Option<IntrinsicSizes>,
 }
 
 impl LayoutBox {
@@ -85,6 +150,28 @@ impl LayoutBox {
             box_type,
             dimensions: Default::default(), // initially set all fields to 0.0
             children: Vec::new(),
+            intrinsic_sizes: None,
+        }
+    }
+
+    fn is_block(&self) -> bool {
+        matches!(self.box_type, BoxType::Block(_))
+    }
+
+    pub(crate) fn dimensions(&self) -> &Dimensions {
+        &self.dimensions
+    }
+
+    pub(crate) fn children(&self) -> &[LayoutBox] {
+        &self.children
+    }
+
+    /// The DOM node this box renders, if any -- `None` for an anonymous box, which exists purely
+    /// to hold other boxes and has no node (and so no style or text) of its own.
+    pub(crate) fn node(&self) -> Option<&NodeRef> {
+        match &self.box_type {
+            BoxType::Block(node) | BoxType::Inline(node) => Some(node),
+            BoxType::Anonymous => None,
         }
     }
 
@@ -115,4 +202,124 @@ pub enum BoxType {
     Block(NodeRef),
     Inline(NodeRef),
     Anonymous,
+}
+
+/// Dumps a text representation of a laid-out (or not-yet-laid-out) box tree, one box per line,
+/// each nested two spaces deeper than its parent -- what `--dump-layout` prints, and what the
+/// tests in this module diff their box trees against.
+pub trait DumpLayout {
+    fn dump_layout(&self, write: &mut dyn Write, indent: usize);
+}
+
+impl DumpLayout for LayoutBox {
+    fn dump_layout(&self, write: &mut dyn Write, indent: usize) {
+        let kind = match &self.box_type {
+            BoxType::Block(_) => "Block",
+            BoxType::Inline(_) => "Inline",
+            BoxType::Anonymous => "Anonymous",
+        };
+        let content = &self.dimensions.content;
+        // Ignore write errors (e.g. a broken pipe from `--dump-layout | head`) rather than
+        // panicking partway through the dump.
+        let _ = writeln!(
+            write,
+            "{:indent$}{} ({}, {}) {}x{}",
+            "",
+            kind,
+            content.x,
+            content.y,
+            content.width,
+            content.height,
+            indent = indent,
+        );
+        for child in &self.children {
+            child.dump_layout(write, indent + 2);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dom::parser::parse_html;
+    use crate::dom::traits::TendrilSink;
+    use crate::style::apply_styles;
+    use crate::style::stylesheet::parse_css_to_stylesheet;
+    use std::io::Cursor;
+
+    /// Parses `html`, styles it with `css` (a minimal author stylesheet -- no UA sheet, so every
+    /// `display` this test cares about has to be spelled out), and returns its `<body>`'s first
+    /// child, styled and ready for `build_layout_tree_fragments`.
+    fn styled_first_body_child(html: &[u8], css: &str) -> NodeRef {
+        let dom = parse_html()
+            .from_utf8()
+            .read_from(&mut Cursor::new(html.to_vec()))
+            .unwrap();
+        let stylesheet =
+            parse_css_to_stylesheet(None, &mut css.to_owned()).expect("parse stylesheet fail");
+        apply_styles(dom.clone(), &[stylesheet], &[], &[]);
+        let html_element = dom.children().next().expect("html element");
+        // html5ever always inserts a `<head>` before `<body>`.
+        let body = html_element.children().nth(1).expect("body element");
+        body.children().next().expect("body's first child")
+    }
+
+    fn dump(layout_box: &LayoutBox) -> String {
+        let mut buf = Vec::new();
+        layout_box.dump_layout(&mut buf, 0);
+        String::from_utf8(buf).unwrap()
+    }
+
+    fn dump_all(layout_boxes: &[LayoutBox]) -> String {
+        layout_boxes.iter().map(dump).collect()
+    }
+
+    /// An in-flow block (`<div>`) nested inside an inline box (`<span>`) splits the span around
+    /// it instead of nesting under it: `[span fragment before, block, span fragment after]`, per
+    /// https://www.w3.org/TR/CSS2/visuren.html#box-gen. The span's block-level ancestor is what
+    /// wraps each span fragment in an anonymous block.
+    #[test]
+    fn splits_inline_text_then_block_then_inline_text() {
+        let span = styled_first_body_child(
+            b"<span>before<div>block</div>after</span>",
+            "span { display: inline; } div { display: block; }",
+        );
+
+        let fragments = build_layout_tree_fragments(span);
+        assert_eq!(
+            dump_all(&fragments),
+            "Inline (0, 0) 0x0\n\
+             \x20\x20Inline (0, 0) 0x0\n\
+             Block (0, 0) 0x0\n\
+             \x20\x20Anonymous (0, 0) 0x0\n\
+             \x20\x20\x20\x20Inline (0, 0) 0x0\n\
+             Inline (0, 0) 0x0\n\
+             \x20\x20Inline (0, 0) 0x0\n"
+        );
+    }
+
+    /// Two consecutive in-flow blocks nested inside an inline box each force a split, which
+    /// leaves an empty `Inline(span)` placeholder fragment between them (and before/after) even
+    /// though no inline content is actually there.
+    #[test]
+    fn two_consecutive_blocks_inside_inline_leave_empty_inline_placeholders() {
+        let span = styled_first_body_child(
+            b"<span><div>a</div><div>b</div></span>",
+            "span { display: inline; } div { display: block; }",
+        );
+
+        let fragments = build_layout_tree_fragments(span);
+        assert_eq!(
+            dump_all(&fragments),
+            "Inline (0, 0) 0x0\n\
+             Block (0, 0) 0x0\n\
+             \x20\x20Anonymous (0, 0) 0x0\n\
+             \x20\x20\x20\x20Inline (0, 0) 0x0\n\
+             Inline (0, 0) 0x0\n\
+             Block (0, 0) 0x0\n\
+             \x20\x20Anonymous (0, 0) 0x0\n\
+             \x20\x20\x20\x20Inline (0, 0) 0x0\n\
+             Inline (0, 0) 0x0\n"
+        );
+    }
 }
\ No newline at end of file
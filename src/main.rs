@@ -11,7 +11,7 @@ extern crate strum_macros;
 #[macro_use]
 extern crate derive_builder;
 
-use std::fs::File;
+use std::io::Cursor;
 
 use crate::dom::parser::parse_html;
 use crate::dom::traits::TendrilSink;
@@ -27,16 +27,21 @@ pub mod common;
 pub mod dom;
 pub mod gfx;
 pub mod layout;
+pub mod net;
 pub mod style;
 
 use crate::cli::{
-    dump_layout_tree, html_file_path_from_files, inner_window_height, inner_window_width,
-    scale_factor, setup_and_get_cli_args, stylesheets_from_files,
+    document_url_from_args, dump_layout_tree, inner_window_height, inner_window_width,
+    scale_factor, setup_and_get_cli_args, stylesheet_urls_from_args,
 };
-use crate::gfx::char::CharHandle;
+use crate::net::{fetch, parse_url_or_file_path};
+use crate::style::restyle_damage::RestyleDamage;
 use crate::gfx::display::build_display_list;
+use crate::gfx::font::FontContext;
 use crate::gfx::paint::MasterPainter;
-use crate::gfx::{init_main_window_and_gl, print_gl_info, resize_window};
+use crate::gfx::paint_task::PaintTaskHandle;
+use crate::gfx::{init_main_window_and_gl, print_gl_info};
+use glutin::dpi::PhysicalSize;
 use crate::layout::layout_box::LayoutBox;
 pub use common::Side;
 use gl::Gl;
@@ -52,24 +57,46 @@ use glutin::{PossiblyCurrent, WindowedContext};
 fn main() {
     let arg_matches = setup_and_get_cli_args();
     let fallback_local_html = "tests/websrc/rainbow-divs.html";
-    let html_file = html_file_path_from_files(&arg_matches).unwrap_or(fallback_local_html);
+    let document_url = document_url_from_args(&arg_matches)
+        .unwrap_or_else(|| parse_url_or_file_path(fallback_local_html).expect("fallback document url"));
+    let document_bytes = fetch(&document_url).expect("document fetch fail");
     let dom = parse_html()
         .from_utf8()
-        .read_from(&mut File::open(html_file).unwrap())
+        .read_from(&mut Cursor::new(document_bytes))
         .unwrap();
+
+    let ua_sheet_url = parse_url_or_file_path("web/browser.css").expect("browser.css url");
     let ua_sheet = style::stylesheet::parse_css_to_stylesheet(
-        Some("browser.css".to_owned()),
-        &mut std::fs::read_to_string("web/browser.css").expect("file fail"),
+        Some(ua_sheet_url.clone()),
+        &mut String::from_utf8(fetch(&ua_sheet_url).expect("browser.css fetch fail"))
+            .expect("browser.css is not utf8"),
     )
     .expect("parse stylesheet fail");
-    let author_sheets = stylesheets_from_files(&arg_matches).unwrap_or_else(|| {
-        vec![style::stylesheet::parse_css_to_stylesheet(
-            Some("rainbow-divs.css".to_owned()),
-            &mut std::fs::read_to_string("tests/websrc/rainbow-divs.css").expect("file fail"),
-        )
-        .expect("parse stylesheet fail")]
-    });
-    apply_styles(dom.clone(), &[ua_sheet], &[], &author_sheets);
+    let author_sheets = stylesheet_urls_from_args(&arg_matches, &document_url)
+        .map(|urls| {
+            urls.into_iter()
+                .map(|url| {
+                    let mut css = String::from_utf8(fetch(&url).expect("stylesheet fetch fail"))
+                        .expect("stylesheet is not utf8");
+                    style::stylesheet::parse_css_to_stylesheet(Some(url), &mut css)
+                        .expect("parse stylesheet fail")
+                })
+                .collect()
+        })
+        .unwrap_or_else(|| {
+            let fallback_url =
+                parse_url_or_file_path("tests/websrc/rainbow-divs.css").expect("fallback css url");
+            vec![style::stylesheet::parse_css_to_stylesheet(
+                Some(fallback_url.clone()),
+                &mut String::from_utf8(fetch(&fallback_url).expect("fetch fail"))
+                    .expect("rainbow-divs.css is not utf8"),
+            )
+            .expect("parse stylesheet fail")]
+        });
+    // First-ever styling pass always comes back RECONSTRUCT_FLOW (there's no previous computed
+    // values to diff against), but `run_event_loop` consults it the same way a later, truly
+    // incremental restyle would.
+    let initial_damage = apply_styles(dom.clone(), &[ua_sheet], &[], &author_sheets);
     let (inner_width_opt, inner_height_opt) = (
         inner_window_width(&arg_matches),
         inner_window_height(&arg_matches),
@@ -85,7 +112,14 @@ fn main() {
     let (windowed_context, event_loop, gl) =
         init_main_window_and_gl(inner_width_opt, inner_height_opt);
     print_gl_info(&windowed_context, &gl);
-    run_event_loop(event_loop, gl, dom, windowed_context, scale_factor_opt);
+    run_event_loop(
+        event_loop,
+        gl,
+        dom,
+        initial_damage,
+        windowed_context,
+        scale_factor_opt,
+    );
 }
 
 fn run_layout_dump(
@@ -110,23 +144,48 @@ pub fn run_event_loop(
     event_loop: EventLoop<()>,
     gl: Gl,
     styled_dom: NodeRef,
+    initial_damage: RestyleDamage,
     windowed_context: WindowedContext<PossiblyCurrent>,
     cli_specified_scale_factor: Option<f32>,
 ) {
-    let mut master_painter = MasterPainter::new(&gl).unwrap();
-    let char_handle = CharHandle::new(&gl);
-    // An un-laid-out tree of boxes, to be cloned from whenever a global layout is required.
-    // This saves us from having to rebuild the entire layout tree from the DOM when necessary,
-    // instead only needing a clone.
+    let master_painter = MasterPainter::new(&gl).unwrap();
+    // `FontContext` keeps its own `Gl` (cloned here) rather than borrowing `gl`, so it isn't
+    // tied to the lifetime of the handle that moves into the paint task below -- see
+    // `FontContext`'s own doc comment for why resolving/caching font groups through it from this
+    // thread is only a placeholder until real glyph rasterization needs a GL context this thread
+    // doesn't have.
+    let font_context = FontContext::new(gl.clone());
+    // An un-laid-out tree of boxes, to be cloned from whenever a global layout is required. This
+    // saves us from having to rebuild the entire layout tree from the DOM when necessary, instead
+    // only needing a clone. It only needs rebuilding when restyling damaged the flow tree itself
+    // (RECONSTRUCT_FLOW); lesser damage (REFLOW, REPAINT) can reuse it as-is.
+    assert!(
+        initial_damage.contains(RestyleDamage::RECONSTRUCT_FLOW),
+        "first styling pass always reconstructs the flow tree"
+    );
     let clean_layout_tree = build_layout_tree(styled_dom).unwrap();
     let mut scale =
         cli_specified_scale_factor.unwrap_or(windowed_context.window().scale_factor() as f32);
-    paint(
-        clean_layout_tree.clone(),
-        &windowed_context,
-        &char_handle,
-        &mut master_painter,
+    let mut viewport_size = windowed_context.window().inner_size();
+    // The previous frame's fully laid-out tree, reused as-is for damage that's `REPAINT`-only --
+    // `None` until the first `layout_and_paint` call below fills it in.
+    let mut last_laid_out_tree: Option<LayoutBox> = None;
+
+    // Hand the GL context (and `gl`, needed to resize its viewport) off to a dedicated paint
+    // task, so expensive draw calls never block this thread from processing window events.
+    let windowed_context = windowed_context
+        .make_not_current()
+        .expect("failed to release GL context for the paint task");
+    let paint_task = PaintTaskHandle::spawn(windowed_context, gl, master_painter);
+
+    layout_and_paint(
+        &clean_layout_tree,
+        &mut last_laid_out_tree,
+        &font_context,
+        &paint_task,
+        viewport_size,
         scale,
+        initial_damage,
     );
     event_loop.run(move |event, _, control_flow| {
         // println!("{:?}", event);
@@ -135,13 +194,18 @@ pub fn run_event_loop(
             Event::LoopDestroyed => {}
             Event::WindowEvent { ref event, .. } => match event {
                 WindowEvent::Resized(physical_size) => {
-                    resize_window(&gl, &windowed_context, physical_size);
-                    paint(
-                        clean_layout_tree.clone(),
-                        &windowed_context,
-                        &char_handle,
-                        &mut master_painter,
+                    viewport_size = *physical_size;
+                    paint_task.resize(viewport_size);
+                    // The viewport itself changed, not any node's style, but that's still a
+                    // layout-affecting change -- same bucket of work as an in-flow `REFLOW`.
+                    layout_and_paint(
+                        &clean_layout_tree,
+                        &mut last_laid_out_tree,
+                        &font_context,
+                        &paint_task,
+                        viewport_size,
                         scale,
+                        RestyleDamage::REFLOW,
                     )
                 }
                 WindowEvent::ScaleFactorChanged {
@@ -149,13 +213,16 @@ pub fn run_event_loop(
                     new_inner_size,
                 } => {
                     scale = *scale_factor as f32;
-                    resize_window(&gl, &windowed_context, new_inner_size);
-                    paint(
-                        clean_layout_tree.clone(),
-                        &windowed_context,
-                        &char_handle,
-                        &mut master_painter,
+                    viewport_size = **new_inner_size;
+                    paint_task.resize(viewport_size);
+                    layout_and_paint(
+                        &clean_layout_tree,
+                        &mut last_laid_out_tree,
+                        &font_context,
+                        &paint_task,
+                        viewport_size,
                         scale,
+                        RestyleDamage::REFLOW,
                     )
                 }
                 WindowEvent::CloseRequested => *control_flow = ControlFlow::Exit,
@@ -165,21 +232,43 @@ pub fn run_event_loop(
         }
     });
 
-    fn paint(
-        mut layout_tree: LayoutBox,
-        windowed_context: &WindowedContext<PossiblyCurrent>,
-        char_handle: &CharHandle,
-        painter: &mut MasterPainter,
+    /// Paints `clean_layout_tree`'s current frame, consulting `damage` to decide how much of the
+    /// pipeline above painting actually needs to re-run.
+    ///
+    /// Damage that only ever reaches `REPAINT` (nothing moved or resized) reuses
+    /// `last_laid_out_tree`'s positions verbatim, skipping `global_layout` entirely. Anything
+    /// that can affect layout (`REFLOW`, `REFLOW_OUT_OF_FLOW`, `RECONSTRUCT_FLOW`) -- or simply
+    /// not having laid out a frame yet -- clones `clean_layout_tree` and lays it out fresh,
+    /// caching the result in `last_laid_out_tree` for the next pure-repaint frame to reuse.
+    fn layout_and_paint(
+        clean_layout_tree: &LayoutBox,
+        last_laid_out_tree: &mut Option<LayoutBox>,
+        font_context: &FontContext,
+        paint_task: &PaintTaskHandle,
+        viewport_size: PhysicalSize<u32>,
         scale_factor: f32,
+        damage: RestyleDamage,
     ) {
-        let inner_window_size = windowed_context.window().inner_size();
-        global_layout(
-            &mut layout_tree,
-            inner_window_size.width as f32,
-            inner_window_size.width as f32,
-            scale_factor,
-        );
-        let display_list = build_display_list(&layout_tree, &char_handle, scale_factor);
-        painter.paint(&windowed_context, &display_list);
+        let needs_layout = last_laid_out_tree.is_none()
+            || damage.intersects(
+                RestyleDamage::RECONSTRUCT_FLOW
+                    | RestyleDamage::REFLOW_OUT_OF_FLOW
+                    | RestyleDamage::REFLOW,
+            );
+        if needs_layout {
+            let mut layout_tree = clean_layout_tree.clone();
+            global_layout(
+                &mut layout_tree,
+                viewport_size.width as f32,
+                viewport_size.width as f32,
+                scale_factor,
+            );
+            *last_laid_out_tree = Some(layout_tree);
+        }
+        let layout_tree = last_laid_out_tree
+            .as_ref()
+            .expect("just laid out above if it wasn't already cached");
+        let display_list = build_display_list(layout_tree, &font_context, scale_factor);
+        paint_task.paint(display_list, scale_factor);
     }
 }
@@ -0,0 +1,125 @@
+//! Resource fetching for Kosmonaut.
+//!
+//! Everything Kosmonaut loads off the network or disk -- the document itself, author
+//! stylesheets, `@import`ed stylesheets, and (eventually) images -- goes through this module.
+//! A [`Provider`] knows how to turn a single [`Url`] into bytes; [`fetch`] picks the right
+//! provider for a URL's scheme.
+
+use std::fs;
+use std::io;
+
+use url::Url;
+
+/// Parses a CLI-provided string into a URL, treating bare paths as `file://` URLs relative to
+/// the current working directory.
+pub fn parse_url_or_file_path(input: &str) -> Result<Url, NetError> {
+    match Url::parse(input) {
+        Ok(url) => Ok(url),
+        Err(url::ParseError::RelativeUrlWithoutBase) => {
+            let cwd = std::env::current_dir().map_err(NetError::Io)?;
+            Url::from_file_path(cwd.join(input))
+                .map_err(|_| NetError::InvalidUrl(input.to_owned()))
+        }
+        Err(e) => Err(NetError::UrlParse(e)),
+    }
+}
+
+/// Resolves `relative` against `base`, as when a stylesheet references another stylesheet via
+/// `@import` or a document references its stylesheets.
+pub fn resolve_url(base: &Url, relative: &str) -> Result<Url, NetError> {
+    base.join(relative)
+        .map_err(|_| NetError::InvalidUrl(relative.to_owned()))
+}
+
+/// Fetches the bytes at `url`, dispatching to the provider for its scheme.
+pub fn fetch(url: &Url) -> Result<Vec<u8>, NetError> {
+    match url.scheme() {
+        "file" => FileProvider.fetch(url),
+        "http" | "https" => HttpProvider.fetch(url),
+        other => Err(NetError::UnsupportedScheme(other.to_owned())),
+    }
+}
+
+/// Something that can retrieve the bytes backing a URL.
+pub trait Provider {
+    fn fetch(&self, url: &Url) -> Result<Vec<u8>, NetError>;
+}
+
+/// Reads `file://` URLs off the local filesystem.
+struct FileProvider;
+
+impl Provider for FileProvider {
+    fn fetch(&self, url: &Url) -> Result<Vec<u8>, NetError> {
+        let path = url
+            .to_file_path()
+            .map_err(|_| NetError::InvalidUrl(url.to_string()))?;
+        fs::read(path).map_err(NetError::Io)
+    }
+}
+
+/// Retrieves `http://`/`https://` URLs via a blocking `reqwest` request.
+struct HttpProvider;
+
+impl Provider for HttpProvider {
+    fn fetch(&self, url: &Url) -> Result<Vec<u8>, NetError> {
+        let response = reqwest::blocking::get(url.clone()).map_err(NetError::Http)?;
+        let response = response.error_for_status().map_err(NetError::Http)?;
+        Ok(response.bytes().map_err(NetError::Http)?.to_vec())
+    }
+}
+
+#[derive(Debug)]
+pub enum NetError {
+    Io(io::Error),
+    Http(reqwest::Error),
+    UrlParse(url::ParseError),
+    InvalidUrl(String),
+    UnsupportedScheme(String),
+}
+
+impl std::fmt::Display for NetError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            NetError::Io(e) => write!(f, "io error: {}", e),
+            NetError::Http(e) => write!(f, "http error: {}", e),
+            NetError::UrlParse(e) => write!(f, "could not parse url: {}", e),
+            NetError::InvalidUrl(url) => write!(f, "invalid url: {}", url),
+            NetError::UnsupportedScheme(scheme) => {
+                write!(f, "unsupported url scheme: {}", scheme)
+            }
+        }
+    }
+}
+
+impl std::error::Error for NetError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_url_or_file_path_passes_through_absolute_urls() {
+        let url = parse_url_or_file_path("https://example.com/style.css").unwrap();
+        assert_eq!(url.as_str(), "https://example.com/style.css");
+    }
+
+    #[test]
+    fn parse_url_or_file_path_resolves_bare_paths_against_cwd() {
+        let url = parse_url_or_file_path("web/browser.css").unwrap();
+        assert_eq!(url.scheme(), "file");
+        assert!(url.path().ends_with("/web/browser.css"));
+    }
+
+    #[test]
+    fn resolve_url_joins_relative_against_base() {
+        let base = Url::parse("https://example.com/css/main.css").unwrap();
+        let resolved = resolve_url(&base, "reset.css").unwrap();
+        assert_eq!(resolved.as_str(), "https://example.com/css/reset.css");
+    }
+
+    #[test]
+    fn resolve_url_rejects_unparseable_relative() {
+        let base = Url::parse("https://example.com/css/main.css").unwrap();
+        assert!(resolve_url(&base, "http://[::1").is_err());
+    }
+}
@@ -0,0 +1,84 @@
+//! Style recalculation.
+//!
+//! Walks the DOM computing (or recomputing) each node's `ComputedValues`, skipping a subtree
+//! entirely when nothing above it forced re-inheritance and it already has computed values from a
+//! previous pass. Returns the aggregate `RestyleDamage` so callers know how much of the pipeline
+//! downstream of styling (box tree construction, layout, paint) actually needs to rerun.
+//!
+//! TODO(#chunk0-4 follow-up): "nothing above it forced re-inheritance" is currently the only skip
+//! condition -- once Kosmonaut has a selector-matching engine, a node should also be skipped when
+//! its matched rules are unchanged, even if a sibling subtree was damaged.
+
+use crate::dom::tree::NodeRef;
+use crate::style::restyle_damage::RestyleDamage;
+use crate::style::stylesheet::Stylesheet;
+use crate::style::values::computed::ComputedValues;
+
+pub fn apply_styles(
+    dom: NodeRef,
+    ua_sheets: &[Stylesheet],
+    user_sheets: &[Stylesheet],
+    author_sheets: &[Stylesheet],
+) -> RestyleDamage {
+    // `cascade` (and, in turn, `ComputedValues::cascade`) only knows about plain style rules --
+    // flatten each sheet's `@import`s into it first, so imported rules actually take part in the
+    // cascade instead of being silently ignored.
+    let ua_sheets: Vec<Stylesheet> = ua_sheets.iter().map(Stylesheet::flattened).collect();
+    let user_sheets: Vec<Stylesheet> = user_sheets.iter().map(Stylesheet::flattened).collect();
+    let author_sheets: Vec<Stylesheet> = author_sheets.iter().map(Stylesheet::flattened).collect();
+    recalc_style(
+        dom,
+        &ua_sheets,
+        &user_sheets,
+        &author_sheets,
+        RestyleDamage::empty(),
+    )
+}
+
+fn recalc_style(
+    node: NodeRef,
+    ua_sheets: &[Stylesheet],
+    user_sheets: &[Stylesheet],
+    author_sheets: &[Stylesheet],
+    inherited_damage: RestyleDamage,
+) -> RestyleDamage {
+    let previous = node.computed_values().clone();
+    let already_styled = previous.is_some();
+    let must_recompute = !already_styled || inherited_damage.propagates_to_children();
+
+    let own_damage = if must_recompute {
+        let new_computed = cascade(&node, ua_sheets, user_sheets, author_sheets);
+        let damage = RestyleDamage::compute(previous.as_ref(), &new_computed);
+        *node.computed_values_mut() = Some(new_computed);
+        damage
+    } else {
+        RestyleDamage::empty()
+    };
+    let damage = own_damage | inherited_damage;
+
+    let damage_to_propagate = if damage.propagates_to_children() {
+        damage
+    } else {
+        RestyleDamage::empty()
+    };
+    node.children().fold(damage, |acc, child| {
+        acc | recalc_style(
+            child,
+            ua_sheets,
+            user_sheets,
+            author_sheets,
+            damage_to_propagate,
+        )
+    })
+}
+
+/// Matches `node` against `ua_sheets`/`user_sheets`/`author_sheets` in cascade order and computes
+/// its `ComputedValues`.
+fn cascade(
+    node: &NodeRef,
+    ua_sheets: &[Stylesheet],
+    user_sheets: &[Stylesheet],
+    author_sheets: &[Stylesheet],
+) -> ComputedValues {
+    ComputedValues::cascade(node, ua_sheets, user_sheets, author_sheets)
+}
@@ -0,0 +1,5 @@
+pub mod apply;
+pub mod restyle_damage;
+pub mod stylesheet;
+
+pub use apply::apply_styles;
@@ -0,0 +1,168 @@
+//! Restyle damage: the minimal amount of downstream work a style change actually requires.
+//!
+//! Comparing a node's freshly computed values against whatever it computed last time produces a
+//! `RestyleDamage`, which later stages (box tree construction, layout, paint) consult instead of
+//! unconditionally redoing everything on every restyle. `RestyleDamage::compute` leans on
+//! `ComputedValues::out_of_flow_position_differs`/`in_flow_layout_differs`/
+//! `inherited_properties_differ`, defined below, to tell a paint-only property change (e.g.
+//! `color`) apart from one that actually moves or resizes boxes, and either apart from one that
+//! has to propagate to descendants regardless of whether this box's own layout changed.
+
+use crate::style::values::computed::ComputedValues;
+
+impl ComputedValues {
+    /// Whether `self` and `other` disagree on anything that moves or resizes an out-of-flow
+    /// (`position: absolute`/`fixed`) descendant: its `position` itself, or one of the box-offset
+    /// properties (`top`/`right`/`bottom`/`left`) that only take effect once a box is out of flow.
+    fn out_of_flow_position_differs(&self, other: &ComputedValues) -> bool {
+        self.position != other.position
+            || self.top != other.top
+            || self.right != other.right
+            || self.bottom != other.bottom
+            || self.left != other.left
+    }
+
+    /// Whether `self` and `other` disagree on anything that changes this box's own in-flow size
+    /// or the space it reserves around itself: `width`/`height`, or any margin/padding/border
+    /// edge.
+    fn in_flow_layout_differs(&self, other: &ComputedValues) -> bool {
+        self.width != other.width
+            || self.height != other.height
+            || self.margin_top != other.margin_top
+            || self.margin_right != other.margin_right
+            || self.margin_bottom != other.margin_bottom
+            || self.margin_left != other.margin_left
+            || self.padding_top != other.padding_top
+            || self.padding_right != other.padding_right
+            || self.padding_bottom != other.padding_bottom
+            || self.padding_left != other.padding_left
+            || self.border_top_width != other.border_top_width
+            || self.border_right_width != other.border_right_width
+            || self.border_bottom_width != other.border_bottom_width
+            || self.border_left_width != other.border_left_width
+    }
+
+    /// Whether `self` and `other` disagree on a property that's inherited by default -- one a
+    /// descendant that doesn't set it itself picks up from its nearest ancestor's computed value,
+    /// rather than its initial value. A change here has to propagate to every such descendant, or
+    /// they keep computing against the stale inherited value forever; it's unrelated to whether
+    /// `self`'s own box-model or out-of-flow properties changed.
+    ///
+    /// `font_family` is the only inherited property `ComputedValues` models so far; grow this
+    /// alongside it (`color`, `font-size`, ... once those are threaded through cascading).
+    fn inherited_properties_differ(&self, other: &ComputedValues) -> bool {
+        self.font_family != other.font_family
+    }
+}
+
+bitflags::bitflags! {
+    #[derive(Default)]
+    pub struct RestyleDamage: u8 {
+        /// This node needs repainting. `compute` sets it for every change, alongside whichever
+        /// other flags apply; when it is the only flag, only a paint-time property (e.g.
+        /// `color`, `background-color`) changed.
+        const REPAINT = 0b00001;
+        /// An out-of-flow descendant (e.g. `position: absolute`) needs to be laid out again, but
+        /// in-flow layout of this subtree is unaffected.
+        const REFLOW_OUT_OF_FLOW = 0b00010;
+        /// This box's own layout needs to be recomputed.
+        const REFLOW = 0b00100;
+        /// The box tree itself is stale (e.g. `display` changed) and must be rebuilt before
+        /// anything else can happen.
+        const RECONSTRUCT_FLOW = 0b01000;
+        /// An inherited property (of those `ComputedValues` models, only `font-family`) changed,
+        /// so descendants that inherit it need their own computed values recomputed --
+        /// independent of whether this box's own layout changed.
+        const INHERITED = 0b10000;
+    }
+}
+
+impl RestyleDamage {
+    /// Computes the damage produced by a node's computed style changing from `old` to `new`.
+    /// `old` being `None` means this is the node's first styling pass, which always demands a
+    /// full flow reconstruction.
+    pub fn compute(old: Option<&ComputedValues>, new: &ComputedValues) -> RestyleDamage {
+        let old = match old {
+            None => return RestyleDamage::RECONSTRUCT_FLOW,
+            Some(old) => old,
+        };
+        if old.display != new.display {
+            return RestyleDamage::RECONSTRUCT_FLOW;
+        }
+        if old == new {
+            return RestyleDamage::empty();
+        }
+
+        // Something changed; at minimum, whatever painted this node needs to run again.
+        let mut damage = RestyleDamage::REPAINT;
+        if old.out_of_flow_position_differs(new) {
+            damage |= RestyleDamage::REFLOW_OUT_OF_FLOW;
+        }
+        if old.in_flow_layout_differs(new) {
+            damage |= RestyleDamage::REFLOW;
+        }
+        if old.inherited_properties_differ(new) {
+            damage |= RestyleDamage::INHERITED;
+        }
+        damage
+    }
+
+    /// Whether this damage forces descendants to be considered damaged too, because their
+    /// computed values inherit from this node's.
+    ///
+    /// This is exactly `INHERITED`: `REFLOW`/`RECONSTRUCT_FLOW` mean this box's own layout (or
+    /// the box tree itself) needs redoing, which has nothing to do with whether any descendant's
+    /// *inherited* computed values are now stale.
+    pub fn propagates_to_children(self) -> bool {
+        self.contains(RestyleDamage::INHERITED)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::style::values::computed::Display;
+
+    fn base() -> ComputedValues {
+        ComputedValues::default()
+    }
+
+    #[test]
+    fn first_pass_reconstructs_flow() {
+        assert_eq!(
+            RestyleDamage::compute(None, &base()),
+            RestyleDamage::RECONSTRUCT_FLOW
+        );
+    }
+
+    #[test]
+    fn display_change_reconstructs_flow() {
+        let old = base();
+        let mut new = base();
+        new.display = Display::None;
+        assert_eq!(
+            RestyleDamage::compute(Some(&old), &new),
+            RestyleDamage::RECONSTRUCT_FLOW
+        );
+    }
+
+    #[test]
+    fn inherited_only_change_propagates_to_children() {
+        let old = base();
+        let mut new = base();
+        new.font_family = vec!["serif".to_owned()];
+        let damage = RestyleDamage::compute(Some(&old), &new);
+        assert!(damage.contains(RestyleDamage::REPAINT));
+        assert!(damage.propagates_to_children());
+    }
+
+    #[test]
+    fn box_model_only_change_does_not_propagate_to_children() {
+        let old = base();
+        let mut new = base();
+        new.border_top_width = old.border_top_width + 5.0;
+        let damage = RestyleDamage::compute(Some(&old), &new);
+        assert!(damage.contains(RestyleDamage::REFLOW));
+        assert!(!damage.propagates_to_children());
+    }
+}
@@ -0,0 +1,287 @@
+//! CSS stylesheet parsing.
+//!
+//! A [`Stylesheet`] is an ordered list of [`CssRule`]s together with the [`Url`] it was loaded
+//! from (its `url_data`). `url_data` is what relative URLs referenced from within the sheet --
+//! `@import`s, eventually `url()` values -- get resolved against.
+
+use std::collections::HashSet;
+
+use cssparser::{
+    AtRuleParser, AtRuleType, CowRcStr, Parser, ParserInput, QualifiedRuleParser, RuleListParser,
+    SourceLocation,
+};
+use url::Url;
+
+use crate::net::{fetch, resolve_url};
+
+#[derive(Debug, Clone)]
+pub struct Stylesheet {
+    /// The URL this stylesheet was parsed from. `None` for sheets with no meaningful base, e.g.
+    /// those constructed in tests.
+    pub url_data: Option<Url>,
+    pub rules: Vec<CssRule>,
+}
+
+impl Stylesheet {
+    /// Returns this stylesheet's rules with every `@import` spliced in, in place, at the
+    /// position it occurred -- so cascade order is preserved across sheet boundaries.
+    ///
+    /// This ignores each import's media condition (Kosmonaut doesn't evaluate media queries
+    /// yet), so an imported sheet's rules are treated as unconditionally in effect for now.
+    pub fn flatten_rules(&self) -> Vec<&CssRule> {
+        self.rules.iter().fold(Vec::new(), |mut flat, rule| {
+            match rule {
+                CssRule::Style { .. } => flat.push(rule),
+                CssRule::Import(import) => flat.extend(import.stylesheet.flatten_rules()),
+            }
+            flat
+        })
+    }
+
+    /// A copy of this stylesheet with every `@import` replaced, in place, by the imported sheet's
+    /// own (recursively-flattened) rules -- i.e. `flatten_rules()`, but as an owned `Stylesheet`
+    /// so it can be handed to a cascade that only knows about `CssRule::Style`.
+    pub fn flattened(&self) -> Stylesheet {
+        Stylesheet {
+            url_data: self.url_data.clone(),
+            rules: self.flatten_rules().into_iter().cloned().collect(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum CssRule {
+    /// A plain qualified (style) rule: `prelude { block }`, kept as raw text until Kosmonaut
+    /// grows a selector/property parser.
+    Style { prelude: String, block: String },
+    Import(ImportRule),
+}
+
+#[derive(Debug, Clone)]
+pub struct ImportRule {
+    pub url: Url,
+    /// Raw media query list the import was conditioned on, e.g. `"screen and (min-width: 768px)"`.
+    /// Stored for when Kosmonaut grows media query evaluation; until then, imports are treated as
+    /// unconditionally in effect.
+    pub media: Option<String>,
+    pub stylesheet: Stylesheet,
+}
+
+#[derive(Debug)]
+pub struct StylesheetParseError(pub String);
+
+impl std::fmt::Display for StylesheetParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "failed to parse stylesheet: {}", self.0)
+    }
+}
+
+pub fn parse_css_to_stylesheet(
+    url_data: Option<Url>,
+    css: &mut str,
+) -> Result<Stylesheet, StylesheetParseError> {
+    parse_css_to_stylesheet_importing(url_data, css, &HashSet::new())
+}
+
+/// Does the real work of `parse_css_to_stylesheet`, additionally tracking the URLs of every sheet
+/// currently in the process of being imported (an ancestor chain, not just direct importers) so a
+/// self- or mutually-referential `@import` can be rejected instead of recursing forever.
+fn parse_css_to_stylesheet_importing(
+    url_data: Option<Url>,
+    css: &mut str,
+    importing: &HashSet<Url>,
+) -> Result<Stylesheet, StylesheetParseError> {
+    let mut importing = importing.clone();
+    if let Some(url) = &url_data {
+        importing.insert(url.clone());
+    }
+
+    let mut input = ParserInput::new(css);
+    let mut parser = Parser::new(&mut input);
+    let rule_parser = StylesheetRuleParser {
+        url_data: url_data.clone(),
+        importing,
+    };
+    let rules = RuleListParser::new_for_stylesheet(&mut parser, rule_parser)
+        .filter_map(|result| result.ok())
+        .collect();
+    Ok(Stylesheet { url_data, rules })
+}
+
+/// Consumes every remaining token in `input` and returns the raw text it spanned.
+fn consume_to_string<'i, 't>(input: &mut Parser<'i, 't>) -> String {
+    let start = input.position();
+    while input.next().is_ok() {}
+    input.slice_from(start).to_string()
+}
+
+struct StylesheetRuleParser {
+    /// The URL of the sheet currently being parsed, used to resolve `@import` targets.
+    url_data: Option<Url>,
+    /// URLs of this sheet and every sheet that (transitively) imported it, so a cyclical
+    /// `@import` chain can be detected and rejected instead of recursing forever.
+    importing: HashSet<Url>,
+}
+
+impl<'i> QualifiedRuleParser<'i> for StylesheetRuleParser {
+    type Prelude = String;
+    type QualifiedRule = CssRule;
+    type Error = ();
+
+    fn parse_prelude<'t>(
+        &mut self,
+        input: &mut Parser<'i, 't>,
+    ) -> Result<Self::Prelude, cssparser::ParseError<'i, Self::Error>> {
+        Ok(consume_to_string(input))
+    }
+
+    fn parse_block<'t>(
+        &mut self,
+        prelude: Self::Prelude,
+        _location: SourceLocation,
+        input: &mut Parser<'i, 't>,
+    ) -> Result<Self::QualifiedRule, cssparser::ParseError<'i, Self::Error>> {
+        Ok(CssRule::Style {
+            prelude,
+            block: consume_to_string(input),
+        })
+    }
+}
+
+impl<'i> AtRuleParser<'i> for StylesheetRuleParser {
+    type PreludeNoBlock = ImportRule;
+    type PreludeBlock = ();
+    type AtRule = CssRule;
+    type Error = ();
+
+    /// Handles `@import url(...) [media-query-list];`. Any other at-rule is rejected; Kosmonaut
+    /// doesn't understand `@media`, `@font-face`, etc. yet.
+    fn parse_prelude<'t>(
+        &mut self,
+        name: CowRcStr<'i>,
+        input: &mut Parser<'i, 't>,
+    ) -> Result<
+        AtRuleType<Self::PreludeNoBlock, Self::PreludeBlock>,
+        cssparser::ParseError<'i, Self::Error>,
+    > {
+        if !name.eq_ignore_ascii_case("import") {
+            return Err(input.new_custom_error(()));
+        }
+
+        let location = input.current_source_location();
+        let import_url = input.expect_url_or_string()?.as_ref().to_owned();
+        let media = if input.is_exhausted() {
+            None
+        } else {
+            let media = consume_to_string(input).trim().to_owned();
+            if media.is_empty() {
+                None
+            } else {
+                Some(media)
+            }
+        };
+
+        let resolved = self
+            .url_data
+            .as_ref()
+            .and_then(|base| resolve_url(base, &import_url).ok())
+            .or_else(|| Url::parse(&import_url).ok())
+            .ok_or_else(|| location.new_custom_error(()))?;
+
+        if self.importing.contains(&resolved) {
+            // `resolved` is already an ancestor of this sheet in the import chain -- importing it
+            // again would recurse forever. Reject the rule instead of fetching it again.
+            return Err(location.new_custom_error(()));
+        }
+
+        let mut imported_css = String::from_utf8(
+            fetch(&resolved).map_err(|_| location.new_custom_error(()))?,
+        )
+        .map_err(|_| location.new_custom_error(()))?;
+        let imported_stylesheet = parse_css_to_stylesheet_importing(
+            Some(resolved.clone()),
+            &mut imported_css,
+            &self.importing,
+        )
+        .map_err(|_| location.new_custom_error(()))?;
+
+        Ok(AtRuleType::WithoutBlock(ImportRule {
+            url: resolved,
+            media,
+            stylesheet: imported_stylesheet,
+        }))
+    }
+
+    fn rule_without_block(
+        &mut self,
+        prelude: Self::PreludeNoBlock,
+        _location: SourceLocation,
+    ) -> Self::AtRule {
+        CssRule::Import(prelude)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn temp_css_url(name: &str) -> Url {
+        let mut path = std::env::temp_dir();
+        path.push(format!(
+            "kosmonaut-stylesheet-test-{}-{}",
+            std::process::id(),
+            name
+        ));
+        Url::from_file_path(&path).expect("temp dir path is absolute")
+    }
+
+    #[test]
+    fn flattens_an_import_in_place() {
+        let imported_url = temp_css_url("imported.css");
+        fs::write(imported_url.to_file_path().unwrap(), "b { color: blue; }").unwrap();
+
+        let mut main_css = format!(
+            "a {{ color: red; }} @import \"{}\"; c {{ color: green; }}",
+            imported_url
+        );
+        let sheet = parse_css_to_stylesheet(None, &mut main_css).expect("parse main sheet");
+        assert_eq!(sheet.flattened().rules.len(), 3);
+    }
+
+    #[test]
+    fn rejects_a_self_import_cycle() {
+        let url = temp_css_url("self.css");
+        let mut css = format!("@import \"{}\";", url);
+        let sheet = parse_css_to_stylesheet(Some(url), &mut css)
+            .expect("a rejected @import is dropped, not a parse failure");
+        assert!(
+            sheet.rules.is_empty(),
+            "a self-referential @import must be rejected instead of recursed into"
+        );
+    }
+
+    #[test]
+    fn rejects_a_mutual_import_cycle() {
+        let url_a = temp_css_url("mutual-a.css");
+        let url_b = temp_css_url("mutual-b.css");
+        fs::write(
+            url_a.to_file_path().unwrap(),
+            format!("@import \"{}\";", url_b),
+        )
+        .unwrap();
+        fs::write(
+            url_b.to_file_path().unwrap(),
+            format!("@import \"{}\";", url_a),
+        )
+        .unwrap();
+
+        let mut css_a = fs::read_to_string(url_a.to_file_path().unwrap()).unwrap();
+        let sheet = parse_css_to_stylesheet(Some(url_a), &mut css_a)
+            .expect("a rejected @import is dropped, not a parse failure");
+        assert!(
+            sheet.flattened().rules.is_empty(),
+            "a mutual @import cycle must be rejected instead of recursed into forever"
+        );
+    }
+}